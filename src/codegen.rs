@@ -0,0 +1,311 @@
+//! Ahead-of-time backends that transpile a [`Program`] to a standalone
+//! source file instead of running it: a C backend (`--emit c`) and an
+//! x86-64 assembly backend (`--emit asm`). Both walk the optimized
+//! bytecode directly, so a run of `+` already coalesced into `Inc(N)` by
+//! the optimizer emits `tape[p] += N`/`addb $N` rather than a loop, and a
+//! `[-]` already collapsed into `SetZero` emits `tape[p] = 0`/`movb $0`.
+
+use crate::{OpCode, Program};
+
+/// Emits a standalone C translation unit for `program`.
+pub fn emit_c(program: &Program) -> String {
+    let mut out = String::new();
+    out.push_str("#include <stdio.h>\n\n");
+    out.push_str("static unsigned char tape[30000];\n\n");
+    out.push_str("int main(void) {\n");
+    out.push_str("    size_t p = 0;\n\n");
+
+    let mut idx = 0;
+    emit_c_block(&program.bytecode, &mut idx, &mut out, 1);
+
+    out.push_str("\n    return 0;\n");
+    out.push_str("}\n");
+    out
+}
+
+fn emit_c_block(bytecode: &[OpCode], idx: &mut usize, out: &mut String, indent: usize) {
+    let pad = "    ".repeat(indent);
+
+    while *idx < bytecode.len() {
+        match bytecode[*idx] {
+            OpCode::Jump(_) => return,
+            OpCode::Right(n) => out.push_str(&format!("{pad}p += {n};\n")),
+            OpCode::Left(n) => out.push_str(&format!("{pad}p -= {n};\n")),
+            OpCode::Inc(n) => out.push_str(&format!("{pad}tape[p] += {n};\n")),
+            OpCode::Dec(n) => out.push_str(&format!("{pad}tape[p] -= {n};\n")),
+            OpCode::Print => out.push_str(&format!("{pad}putchar(tape[p]);\n")),
+            OpCode::Read => out.push_str(&format!(
+                "{pad}{{ int c = getchar(); tape[p] = c == EOF ? 0 : (unsigned char)c; }}\n"
+            )),
+            OpCode::SetZero => out.push_str(&format!("{pad}tape[p] = 0;\n")),
+            OpCode::SeekZero(step) => out.push_str(&format!("{pad}while (tape[p]) p += {step};\n")),
+            OpCode::JumpIfZero(_) => {
+                out.push_str(&format!("{pad}while (tape[p]) {{\n"));
+                *idx += 1;
+                emit_c_block(bytecode, idx, out, indent + 1);
+                out.push_str(&format!("{pad}}}\n"));
+            }
+        }
+        *idx += 1;
+    }
+}
+
+/// Emits freestanding x86-64 (System V, GNU assembler/AT&T syntax) that
+/// reads/writes the tape cell under the data pointer via the `read`/`write`
+/// syscalls directly, with no libc dependency.
+pub fn emit_asm(program: &Program) -> String {
+    let mut out = String::new();
+    out.push_str(".section .bss\n");
+    out.push_str(".lcomm tape, 30000\n\n");
+    out.push_str(".section .text\n");
+    out.push_str(".globl _start\n");
+    out.push_str("_start:\n");
+    out.push_str("    lea tape(%rip), %rbx\n");
+    out.push_str("    xor %r12, %r12\n\n");
+
+    let mut idx = 0;
+    let mut label = 0;
+    emit_asm_block(&program.bytecode, &mut idx, &mut out, &mut label);
+
+    out.push_str("\n    mov $60, %rax\n");
+    out.push_str("    xor %rdi, %rdi\n");
+    out.push_str("    syscall\n");
+    out
+}
+
+fn emit_asm_block(bytecode: &[OpCode], idx: &mut usize, out: &mut String, label: &mut usize) {
+    while *idx < bytecode.len() {
+        match bytecode[*idx] {
+            OpCode::Jump(_) => return,
+            OpCode::Right(n) => out.push_str(&format!("    addq ${n}, %r12\n")),
+            OpCode::Left(n) => out.push_str(&format!("    subq ${n}, %r12\n")),
+            OpCode::Inc(n) => out.push_str(&format!("    addb ${n}, (%rbx,%r12)\n")),
+            OpCode::Dec(n) => out.push_str(&format!("    subb ${n}, (%rbx,%r12)\n")),
+            OpCode::Print => {
+                out.push_str("    lea (%rbx,%r12), %rsi\n");
+                out.push_str("    mov $1, %rax\n");
+                out.push_str("    mov $1, %rdi\n");
+                out.push_str("    mov $1, %rdx\n");
+                out.push_str("    syscall\n");
+            }
+            OpCode::Read => {
+                let id = *label;
+                *label += 1;
+                out.push_str("    lea (%rbx,%r12), %rsi\n");
+                out.push_str("    mov $0, %rax\n");
+                out.push_str("    mov $0, %rdi\n");
+                out.push_str("    mov $1, %rdx\n");
+                out.push_str("    syscall\n");
+                // `read` returning anything other than 1 byte (EOF or an
+                // error) leaves the destination untouched, so zero the cell
+                // ourselves to match the interpreter's EOF behavior.
+                out.push_str("    cmpq $1, %rax\n");
+                out.push_str(&format!("    je .Lread{id}\n"));
+                out.push_str("    movb $0, (%rbx,%r12)\n");
+                out.push_str(&format!(".Lread{id}:\n"));
+            }
+            OpCode::SetZero => out.push_str("    movb $0, (%rbx,%r12)\n"),
+            OpCode::SeekZero(step) => {
+                let id = *label;
+                *label += 1;
+                out.push_str(&format!(".Lseek{id}:\n"));
+                out.push_str("    cmpb $0, (%rbx,%r12)\n");
+                out.push_str(&format!("    je .Lseekend{id}\n"));
+                if step >= 0 {
+                    out.push_str(&format!("    addq ${step}, %r12\n"));
+                } else {
+                    out.push_str(&format!("    subq ${}, %r12\n", -step));
+                }
+                out.push_str(&format!("    jmp .Lseek{id}\n"));
+                out.push_str(&format!(".Lseekend{id}:\n"));
+            }
+            OpCode::JumpIfZero(_) => {
+                let id = *label;
+                *label += 1;
+                out.push_str(&format!(".Lloop{id}:\n"));
+                out.push_str("    cmpb $0, (%rbx,%r12)\n");
+                out.push_str(&format!("    je .Lend{id}\n"));
+                *idx += 1;
+                emit_asm_block(bytecode, idx, out, label);
+                out.push_str(&format!("    jmp .Lloop{id}\n"));
+                out.push_str(&format!(".Lend{id}:\n"));
+            }
+        }
+        *idx += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compile(source: &str) -> Program {
+        Program::compile(source).unwrap()
+    }
+
+    #[test]
+    fn emits_pointer_and_cell_arithmetic() {
+        let program = compile(">>><+++-");
+        assert!(emit_c(&program).contains("p += 3;"));
+        assert!(emit_c(&program).contains("p -= 1;"));
+        assert!(emit_c(&program).contains("tape[p] += 3;"));
+        assert!(emit_c(&program).contains("tape[p] -= 1;"));
+
+        let asm = emit_asm(&program);
+        assert!(asm.contains("addq $3, %r12"));
+        assert!(asm.contains("subq $1, %r12"));
+        assert!(asm.contains("addb $3, (%rbx,%r12)"));
+        assert!(asm.contains("subb $1, (%rbx,%r12)"));
+    }
+
+    #[test]
+    fn emits_print_and_read_as_io_calls() {
+        let program = compile(".,");
+        let c = emit_c(&program);
+        assert!(c.contains("putchar(tape[p]);"));
+        assert!(c.contains("getchar();"));
+        // EOF must zero the cell, the same as Interpreter::run on EOF.
+        assert!(c.contains("c == EOF ? 0 : (unsigned char)c"));
+
+        // `.`, `,`, and the trailing `exit` syscall each emit one `syscall`.
+        let asm = emit_asm(&program);
+        assert_eq!(asm.matches("syscall").count(), 3);
+        assert!(asm.contains("mov $1, %rax")); // write
+        assert!(asm.contains("mov $0, %rax")); // read
+                                               // A non-1-byte read result (EOF/error) must zero the cell.
+        assert!(asm.contains("cmpq $1, %rax"));
+        assert!(asm.contains("movb $0, (%rbx,%r12)"));
+    }
+
+    #[test]
+    fn emits_clear_loop_as_set_zero() {
+        let program = compile("[-]");
+        assert!(emit_c(&program).contains("tape[p] = 0;"));
+        assert!(emit_asm(&program).contains("movb $0, (%rbx,%r12)"));
+    }
+
+    #[test]
+    fn emits_scan_loop_as_seek_zero() {
+        let program = compile("[>]");
+        assert!(emit_c(&program).contains("while (tape[p]) p += 1;"));
+
+        let asm = emit_asm(&program);
+        assert!(asm.contains(".Lseek0:"));
+        assert!(asm.contains("addq $1, %r12"));
+        assert!(asm.contains(".Lseekend0:"));
+    }
+
+    #[test]
+    fn emits_general_loops_as_nested_while() {
+        let program = compile("+[>+<-]");
+        let c = emit_c(&program);
+        assert!(c.contains("while (tape[p]) {\n"));
+        // The loop body should be indented one level deeper than `while`.
+        assert!(c.contains("        p += 1;\n"));
+
+        let asm = emit_asm(&program);
+        assert!(asm.contains(".Lloop0:"));
+        assert!(asm.contains(".Lend0:"));
+    }
+
+    /// Actually compiles (cc) or assembles+links (as/ld) the emitted source
+    /// and runs the result against `stdin_data`, so a regression in the
+    /// generated read/write sequences shows up as a behavior mismatch, not
+    /// just a missing substring.
+    mod golden_output {
+        use super::*;
+        use std::io::Write as _;
+        use std::process::{Command, Stdio};
+        use std::sync::atomic::{AtomicU32, Ordering};
+
+        static COUNTER: AtomicU32 = AtomicU32::new(0);
+
+        fn unique_path(suffix: &str) -> std::path::PathBuf {
+            let id = COUNTER.fetch_add(1, Ordering::Relaxed);
+            std::env::temp_dir().join(format!(
+                "bf_codegen_test_{}_{id}{suffix}",
+                std::process::id()
+            ))
+        }
+
+        fn run_interpreter(source: &str, stdin_data: &[u8]) -> Vec<u8> {
+            let program = compile(source);
+            let mut output = Vec::new();
+            let mut interpreter: crate::Interpreter<_, _> =
+                crate::Interpreter::new(std::io::Cursor::new(stdin_data.to_vec()), &mut output);
+            interpreter.run(&program).unwrap();
+            output
+        }
+
+        fn run_binary(bin_path: &std::path::Path, stdin_data: &[u8]) -> Vec<u8> {
+            let mut child = Command::new(bin_path)
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .spawn()
+                .unwrap();
+            child.stdin.take().unwrap().write_all(stdin_data).unwrap();
+            let output = child.wait_with_output().unwrap();
+            assert!(output.status.success());
+            output.stdout
+        }
+
+        fn run_c(source: &str, stdin_data: &[u8]) -> Vec<u8> {
+            let src_path = unique_path(".c");
+            let bin_path = unique_path("");
+            std::fs::write(&src_path, emit_c(&compile(source))).unwrap();
+            let status = Command::new("cc")
+                .arg(&src_path)
+                .arg("-o")
+                .arg(&bin_path)
+                .status()
+                .unwrap();
+            assert!(status.success());
+            let output = run_binary(&bin_path, stdin_data);
+            std::fs::remove_file(&src_path).ok();
+            std::fs::remove_file(&bin_path).ok();
+            output
+        }
+
+        fn run_asm(source: &str, stdin_data: &[u8]) -> Vec<u8> {
+            let src_path = unique_path(".s");
+            let bin_path = unique_path("");
+            std::fs::write(&src_path, emit_asm(&compile(source))).unwrap();
+            // Freestanding (no libc): assemble and link straight from `cc`,
+            // which also drives `as`/`ld` for us.
+            let status = Command::new("cc")
+                .args(["-nostdlib", "-static"])
+                .arg(&src_path)
+                .arg("-o")
+                .arg(&bin_path)
+                .status()
+                .unwrap();
+            assert!(status.success());
+            let output = run_binary(&bin_path, stdin_data);
+            std::fs::remove_file(&src_path).ok();
+            std::fs::remove_file(&bin_path).ok();
+            output
+        }
+
+        #[test]
+        fn emitted_backends_match_the_interpreter_on_eof() {
+            // `+` then `,` on empty stdin then `.`: the interpreter zeroes
+            // the cell on EOF, so this should print a `0` byte, not
+            // `getchar()`'s `-1` truncated into `unsigned char` (C) or
+            // whatever was sitting in the cell before the failed read (asm).
+            let source = "+,.";
+            let expected = run_interpreter(source, b"");
+
+            assert_eq!(run_c(source, b""), expected);
+            assert_eq!(run_asm(source, b""), expected);
+        }
+
+        #[test]
+        fn emitted_backends_match_the_interpreter_on_successful_read() {
+            let source = ",.";
+            let expected = run_interpreter(source, b"A");
+
+            assert_eq!(run_c(source, b"A"), expected);
+            assert_eq!(run_asm(source, b"A"), expected);
+        }
+    }
+}
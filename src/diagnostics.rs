@@ -0,0 +1,100 @@
+//! Structured compile errors with source-span information, rendered as
+//! caret-annotated snippets via `codespan-reporting` instead of a bare
+//! string with no location.
+
+use std::ops::Range;
+
+use codespan_reporting::diagnostic::{Diagnostic, Label};
+use codespan_reporting::files::SimpleFile;
+use codespan_reporting::term::termcolor::{ColorChoice, StandardStream};
+use codespan_reporting::term::{self, Config};
+
+/// A byte-offset range into the source text.
+#[derive(Debug, Clone, Copy)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    fn range(self) -> Range<usize> {
+        self.start..self.end
+    }
+}
+
+/// A `compile` failure, carrying the span of the offending bracket.
+#[derive(Debug)]
+pub struct CompileError {
+    pub message: &'static str,
+    pub span: Span,
+}
+
+/// Renders `err` as a source snippet with a caret under the offending
+/// bracket, e.g.:
+///
+/// ```text
+/// error: unmatched right bracket
+///   ┌─ prog.bf:1:5
+///   │
+/// 1 │ ++++]
+///   │     ^
+/// ```
+pub fn report(file_name: &str, source: &str, err: &CompileError) {
+    let writer = StandardStream::stderr(ColorChoice::Auto);
+    term::emit(
+        &mut writer.lock(),
+        &Config::default(),
+        &SimpleFile::new(file_name, source),
+        &build_diagnostic(err),
+    )
+    .expect("failed to render diagnostic");
+}
+
+fn build_diagnostic(err: &CompileError) -> Diagnostic<()> {
+    Diagnostic::error()
+        .with_message(err.message)
+        .with_labels(vec![Label::primary((), err.span.range())])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codespan_reporting::term::termcolor::Buffer;
+
+    fn render(file_name: &str, source: &str, err: &CompileError) -> String {
+        let mut buffer = Buffer::no_color();
+        term::emit(
+            &mut buffer,
+            &Config::default(),
+            &SimpleFile::new(file_name, source),
+            &build_diagnostic(err),
+        )
+        .unwrap();
+        String::from_utf8(buffer.into_inner()).unwrap()
+    }
+
+    #[test]
+    fn renders_message_file_name_and_offending_source() {
+        let err = CompileError {
+            message: "unmatched right bracket",
+            span: Span { start: 4, end: 5 },
+        };
+        let rendered = render("prog.bf", "++++]", &err);
+
+        assert!(rendered.contains("unmatched right bracket"));
+        assert!(rendered.contains("prog.bf"));
+        assert!(rendered.contains("++++]"));
+        assert!(rendered.contains('^'));
+    }
+
+    #[test]
+    fn reports_the_innermost_unmatched_bracket() {
+        // Two opens, no close: `compile` should blame the inner `[`
+        // (the most recently opened one still on the stack), not the
+        // outer one.
+        let err = crate::compile(&crate::parse("+[+[+")).unwrap_err();
+        assert_eq!(err.message, "unmatched left bracket");
+        assert_eq!(err.span.start, 3);
+        assert_eq!(err.span.end, 4);
+    }
+}
@@ -0,0 +1,279 @@
+//! Native code generation backend.
+//!
+//! This mirrors `Interpreter::run`, but instead of dispatching opcodes in a loop it
+//! lowers the bytecode to Cranelift IR once and runs the resulting machine
+//! code directly. Brackets are lowered to a pair of basic blocks (a header
+//! that tests the current cell and jumps past the loop when it is zero, and
+//! the loop body that jumps back to the header). The generated function
+//! takes a pointer to a flat tape buffer and a pointer to the host I/O
+//! streams, and an internal data-pointer variable tracks the current cell
+//! offset from the tape pointer.
+
+use std::ffi::c_void;
+use std::io::{Read, Write};
+use std::mem;
+
+use cranelift_codegen::ir::condcodes::IntCC;
+use cranelift_codegen::ir::{types, AbiParam, InstBuilder, MemFlags, Type};
+use cranelift_codegen::settings::{self, Configurable};
+use cranelift_frontend::{FunctionBuilder, FunctionBuilderContext, Variable};
+use cranelift_jit::{JITBuilder, JITModule};
+use cranelift_module::{default_libcall_names, Linkage, Module};
+
+use crate::{OpCode, Program};
+
+// The data pointer starts in the middle of the tape rather than at offset 0
+// so that a program which moves left before ever moving right (e.g. a
+// leading `<`) still lands in bounds, matching the tree-walking `Tape`'s
+// ability to grow in either direction.
+const TAPE_LEN: usize = 60_000;
+const TAPE_MID: i64 = (TAPE_LEN / 2) as i64;
+
+/// The `,`/`.` host callbacks take a pointer to this as their first
+/// argument, so the generated code can read/write whatever streams `run`
+/// was given instead of being wired to `stdin`/`stdout` at compile time.
+struct HostIo<'a> {
+    input: &'a mut dyn Read,
+    output: &'a mut dyn Write,
+}
+
+extern "C" fn host_putchar(io: *mut c_void, byte: u8) {
+    let io = unsafe { &mut *(io as *mut HostIo) };
+    let _ = io.output.write_all(&[byte]);
+}
+
+extern "C" fn host_getchar(io: *mut c_void) -> u8 {
+    let io = unsafe { &mut *(io as *mut HostIo) };
+    let mut buf = [0u8; 1];
+    match io.input.read(&mut buf) {
+        Ok(1) => buf[0],
+        _ => 0,
+    }
+}
+
+struct Jit {
+    module: JITModule,
+}
+
+impl Jit {
+    fn new() -> Jit {
+        let mut flag_builder = settings::builder();
+        flag_builder.set("use_colocated_libcalls", "false").unwrap();
+        flag_builder.set("is_pic", "false").unwrap();
+        let isa_builder = cranelift_native::builder().expect("host machine is not supported");
+        let isa = isa_builder
+            .finish(settings::Flags::new(flag_builder))
+            .unwrap();
+
+        let mut jit_builder = JITBuilder::with_isa(isa, default_libcall_names());
+        jit_builder.symbol("putchar", host_putchar as *const u8);
+        jit_builder.symbol("getchar", host_getchar as *const u8);
+
+        Jit {
+            module: JITModule::new(jit_builder),
+        }
+    }
+
+    fn compile(&mut self, bytecode: &[OpCode]) -> extern "C" fn(*mut u8, *mut c_void) {
+        let ptr_type = self.module.target_config().pointer_type();
+
+        let mut putchar_sig = self.module.make_signature();
+        putchar_sig.params.push(AbiParam::new(ptr_type));
+        putchar_sig.params.push(AbiParam::new(types::I8));
+        let putchar_id = self
+            .module
+            .declare_function("putchar", Linkage::Import, &putchar_sig)
+            .unwrap();
+
+        let mut getchar_sig = self.module.make_signature();
+        getchar_sig.params.push(AbiParam::new(ptr_type));
+        getchar_sig.returns.push(AbiParam::new(types::I8));
+        let getchar_id = self
+            .module
+            .declare_function("getchar", Linkage::Import, &getchar_sig)
+            .unwrap();
+
+        let mut ctx = self.module.make_context();
+        ctx.func.signature.params.push(AbiParam::new(ptr_type));
+        ctx.func.signature.params.push(AbiParam::new(ptr_type));
+
+        let mut builder_ctx = FunctionBuilderContext::new();
+        let mut fb = FunctionBuilder::new(&mut ctx.func, &mut builder_ctx);
+
+        let putchar_ref = self.module.declare_func_in_func(putchar_id, fb.func);
+        let getchar_ref = self.module.declare_func_in_func(getchar_id, fb.func);
+
+        let entry = fb.create_block();
+        fb.append_block_params_for_function_params(entry);
+        fb.switch_to_block(entry);
+        fb.seal_block(entry);
+
+        let tape_ptr = fb.block_params(entry)[0];
+        let io_ptr = fb.block_params(entry)[1];
+
+        let data_ptr = Variable::from_u32(0);
+        fb.declare_var(data_ptr, ptr_type);
+        let mid = fb.ins().iconst(ptr_type, TAPE_MID);
+        fb.def_var(data_ptr, mid);
+
+        // One (header, after) block pair per currently-open `[`, pushed on
+        // `JumpIfZero` and popped on the matching `Jump`.
+        let mut loops = Vec::new();
+
+        for op in bytecode {
+            match op {
+                OpCode::Right(n) => move_ptr(&mut fb, data_ptr, *n as i64, ptr_type),
+                OpCode::Left(n) => move_ptr(&mut fb, data_ptr, -(*n as i64), ptr_type),
+                OpCode::Inc(n) => {
+                    let addr = cell_addr(&mut fb, tape_ptr, data_ptr);
+                    let cell = fb.ins().load(types::I8, MemFlags::new(), addr, 0);
+                    let cell = fb.ins().iadd_imm(cell, *n as i64);
+                    fb.ins().store(MemFlags::new(), cell, addr, 0);
+                }
+                OpCode::Dec(n) => {
+                    let addr = cell_addr(&mut fb, tape_ptr, data_ptr);
+                    let cell = fb.ins().load(types::I8, MemFlags::new(), addr, 0);
+                    let cell = fb.ins().iadd_imm(cell, -(*n as i64));
+                    fb.ins().store(MemFlags::new(), cell, addr, 0);
+                }
+                OpCode::SetZero => {
+                    let addr = cell_addr(&mut fb, tape_ptr, data_ptr);
+                    let zero = fb.ins().iconst(types::I8, 0);
+                    fb.ins().store(MemFlags::new(), zero, addr, 0);
+                }
+                OpCode::SeekZero(step) => {
+                    let header = fb.create_block();
+                    let body = fb.create_block();
+                    let after = fb.create_block();
+
+                    fb.ins().jump(header, &[]);
+                    fb.switch_to_block(header);
+                    let addr = cell_addr(&mut fb, tape_ptr, data_ptr);
+                    let cell = fb.ins().load(types::I8, MemFlags::new(), addr, 0);
+                    fb.ins().brif(cell, body, &[], after, &[]);
+
+                    fb.switch_to_block(body);
+                    move_ptr(&mut fb, data_ptr, *step as i64, ptr_type);
+                    fb.ins().jump(header, &[]);
+                    fb.seal_block(header);
+
+                    fb.switch_to_block(after);
+                    fb.seal_block(after);
+                }
+                OpCode::Print => {
+                    let addr = cell_addr(&mut fb, tape_ptr, data_ptr);
+                    let cell = fb.ins().load(types::I8, MemFlags::new(), addr, 0);
+                    fb.ins().call(putchar_ref, &[io_ptr, cell]);
+                }
+                OpCode::Read => {
+                    let call = fb.ins().call(getchar_ref, &[io_ptr]);
+                    let byte = fb.inst_results(call)[0];
+                    let addr = cell_addr(&mut fb, tape_ptr, data_ptr);
+                    fb.ins().store(MemFlags::new(), byte, addr, 0);
+                }
+                OpCode::JumpIfZero(_) => {
+                    let header = fb.create_block();
+                    let body = fb.create_block();
+                    let after = fb.create_block();
+
+                    fb.ins().jump(header, &[]);
+                    fb.switch_to_block(header);
+                    let addr = cell_addr(&mut fb, tape_ptr, data_ptr);
+                    let cell = fb.ins().load(types::I8, MemFlags::new(), addr, 0);
+                    fb.ins().brif(cell, body, &[], after, &[]);
+
+                    fb.switch_to_block(body);
+                    loops.push((header, after));
+                }
+                OpCode::Jump(_) => {
+                    let (header, after) = loops.pop().expect("unbalanced brackets");
+                    fb.ins().jump(header, &[]);
+                    fb.seal_block(header);
+                    fb.switch_to_block(after);
+                    fb.seal_block(after);
+                }
+            }
+        }
+
+        fb.ins().return_(&[]);
+        fb.seal_all_blocks();
+        fb.finalize();
+
+        let func_id = self
+            .module
+            .declare_function("bf_main", Linkage::Export, &ctx.func.signature)
+            .unwrap();
+        self.module.define_function(func_id, &mut ctx).unwrap();
+        self.module.clear_context(&mut ctx);
+        self.module.finalize_definitions().unwrap();
+
+        let code = self.module.get_finalized_function(func_id);
+        unsafe { mem::transmute::<*const u8, extern "C" fn(*mut u8, *mut c_void)>(code) }
+    }
+}
+
+fn cell_addr(
+    fb: &mut FunctionBuilder,
+    tape_ptr: cranelift_codegen::ir::Value,
+    data_ptr: Variable,
+) -> cranelift_codegen::ir::Value {
+    let p = fb.use_var(data_ptr);
+    fb.ins().iadd(tape_ptr, p)
+}
+
+/// Adds `delta` to `data_ptr`, clamping the result to `0..TAPE_LEN` so a
+/// program that wanders off either end of the tape can't read or write
+/// outside the backing buffer.
+fn move_ptr(fb: &mut FunctionBuilder, data_ptr: Variable, delta: i64, ptr_type: Type) {
+    let p = fb.use_var(data_ptr);
+    let moved = fb.ins().iadd_imm(p, delta);
+
+    let zero = fb.ins().iconst(ptr_type, 0);
+    let too_low = fb.ins().icmp(IntCC::SignedLessThan, moved, zero);
+    let clamped_low = fb.ins().select(too_low, zero, moved);
+
+    let max = fb.ins().iconst(ptr_type, TAPE_LEN as i64 - 1);
+    let too_high = fb.ins().icmp(IntCC::SignedGreaterThan, clamped_low, max);
+    let clamped = fb.ins().select(too_high, max, clamped_low);
+
+    fb.def_var(data_ptr, clamped);
+}
+
+/// Compile `program` to native code with Cranelift and run it, operating on
+/// a flat tape buffer rather than the interpreter's bidirectional `Tape`.
+/// `,`/`.` read from `input`/write to `output`, the same as
+/// [`crate::Interpreter`], instead of being hardcoded to `stdin`/`stdout`.
+pub fn run(program: &Program, input: &mut dyn Read, output: &mut dyn Write) {
+    let mut jit = Jit::new();
+    let compiled = jit.compile(&program.bytecode);
+    let mut tape = vec![0u8; TAPE_LEN];
+    let mut io = HostIo { input, output };
+    compiled(tape.as_mut_ptr(), &mut io as *mut HostIo as *mut c_void);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    use crate::Program;
+
+    #[test]
+    fn runs_hello_world_and_captures_output() {
+        let source = "++++++++[>++++[>++>+++>+++>+<<<<-]>+>+>->>+[<]<-]>>.>---.+++++++..+++.>>.<-.<.+++.------.--------.>>+.>++.";
+        let program = Program::compile(source).unwrap();
+        let mut output = Vec::new();
+        run(&program, &mut Cursor::new(Vec::new()), &mut output);
+        assert_eq!(String::from_utf8(output).unwrap(), "Hello World!\n");
+    }
+
+    #[test]
+    fn echoes_input_and_zeros_the_cell_on_eof() {
+        let program = Program::compile(",.,.,.,.").unwrap();
+        let mut output = Vec::new();
+        run(&program, &mut Cursor::new(b"ab".to_vec()), &mut output);
+        // Third and fourth `,` read past the end of the input, so the JIT
+        // should zero the cell rather than leave it at its prior value.
+        assert_eq!(output, vec![b'a', b'b', 0, 0]);
+    }
+}
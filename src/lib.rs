@@ -0,0 +1,425 @@
+//! A brainfuck compiler and interpreter, usable as a library so host
+//! programs can embed it without going through a subprocess: compile source
+//! into a [`Program`], then run it on an [`Interpreter`] configured with
+//! whatever input/output streams the host wants.
+
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::marker::PhantomData;
+
+pub mod codegen;
+pub mod diagnostics;
+pub mod jit;
+mod optimize;
+
+use diagnostics::{CompileError, Span};
+
+#[derive(Debug)]
+#[allow(non_camel_case_types)]
+enum TokenKind {
+    T_GT,
+    T_LT,
+    T_PLUS,
+    T_MINUS,
+    T_DOT,
+    T_COMMA,
+    T_LBRACKET,
+    T_RBRACKET,
+}
+
+#[derive(Debug)]
+struct Token {
+    kind: TokenKind,
+    span: Span,
+}
+
+fn parse(code: &str) -> Vec<Token> {
+    let mut tokens = vec![];
+
+    for (start, c) in code.char_indices() {
+        let span = Span {
+            start,
+            end: start + c.len_utf8(),
+        };
+        let kind = match c {
+            '>' => TokenKind::T_GT,
+            '<' => TokenKind::T_LT,
+            '+' => TokenKind::T_PLUS,
+            '-' => TokenKind::T_MINUS,
+            '.' => TokenKind::T_DOT,
+            ',' => TokenKind::T_COMMA,
+            '[' => TokenKind::T_LBRACKET,
+            ']' => TokenKind::T_RBRACKET,
+            _ => continue,
+        };
+        tokens.push(Token { kind, span });
+    }
+
+    tokens
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum OpCode {
+    Right(usize),
+    Left(usize),
+    Inc(u8),
+    Dec(u8),
+    Print,
+    Read,
+    JumpIfZero(usize),
+    Jump(usize),
+    /// Idiomatic `[-]`/`[+]`, replacing the cell with 0 directly.
+    SetZero,
+    /// Idiomatic `[>]`/`[<]`, stepping the data pointer by `step` cells at a
+    /// time until it lands on a zero cell.
+    SeekZero(isize),
+}
+
+fn compile(toks: &[Token]) -> Result<Vec<OpCode>, CompileError> {
+    let mut brackets: Vec<(usize, Span)> = vec![];
+    let mut bytecode = vec![];
+
+    for (idx, tok) in toks.iter().enumerate() {
+        match tok.kind {
+            TokenKind::T_GT => bytecode.push(OpCode::Right(1)),
+            TokenKind::T_LT => bytecode.push(OpCode::Left(1)),
+            TokenKind::T_PLUS => bytecode.push(OpCode::Inc(1)),
+            TokenKind::T_MINUS => bytecode.push(OpCode::Dec(1)),
+            TokenKind::T_DOT => bytecode.push(OpCode::Print),
+            TokenKind::T_COMMA => bytecode.push(OpCode::Read),
+            TokenKind::T_LBRACKET => {
+                brackets.push((idx, tok.span));
+                bytecode.push(OpCode::JumpIfZero(0));
+            }
+            TokenKind::T_RBRACKET => match brackets.pop() {
+                None => {
+                    return Err(CompileError {
+                        message: "unmatched right bracket",
+                        span: tok.span,
+                    })
+                }
+                Some((j, _)) => {
+                    bytecode.push(OpCode::Jump(j));
+                    bytecode[j] = OpCode::JumpIfZero(idx + 1);
+                }
+            },
+        }
+    }
+
+    match brackets.last() {
+        None => Ok(bytecode),
+        Some((_, span)) => Err(CompileError {
+            message: "unmatched left bracket",
+            span: *span,
+        }),
+    }
+}
+
+/// A parsed, compiled, and optimized brainfuck program, ready to be run on
+/// an [`Interpreter`] (or [`jit::run`]).
+pub struct Program {
+    pub(crate) bytecode: Vec<OpCode>,
+}
+
+impl Program {
+    /// Parses and compiles `source`, then runs the bytecode optimizer over
+    /// the result.
+    pub fn compile(source: &str) -> Result<Program, CompileError> {
+        let toks = parse(source);
+        let bytecode = compile(&toks)?;
+        Ok(Program {
+            bytecode: optimize::optimize(&bytecode),
+        })
+    }
+}
+
+/// How `Interpreter` handles `+`/`-` running a cell past either end of its
+/// cell type's range (`0`/`255` for the default `u8` cell).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Overflow {
+    /// For `u8` cells, `255 + 1` becomes `0` and `0 - 1` becomes `255` (the
+    /// classic brainfuck behavior, and the default).
+    Wrap,
+    /// For `u8` cells, `255 + 1` stays `255` and `0 - 1` stays `0`.
+    Saturate,
+}
+
+/// A brainfuck cell value. Implemented for `u8` (the classic 8-bit cell,
+/// and [`Interpreter`]'s default) and `u16`, so a host that needs a wider
+/// range before `+`/`-` wrap can ask for it instead of being stuck with
+/// `u8`.
+pub trait Cell: Copy + Default {
+    fn wrapping_add(self, n: u8) -> Self;
+    fn wrapping_sub(self, n: u8) -> Self;
+    fn saturating_add(self, n: u8) -> Self;
+    fn saturating_sub(self, n: u8) -> Self;
+    fn is_zero(self) -> bool;
+    /// Truncates to the byte written for `.`.
+    fn to_byte(self) -> u8;
+    /// Widens the byte read for `,`.
+    fn from_byte(byte: u8) -> Self;
+}
+
+impl Cell for u8 {
+    fn wrapping_add(self, n: u8) -> Self {
+        u8::wrapping_add(self, n)
+    }
+    fn wrapping_sub(self, n: u8) -> Self {
+        u8::wrapping_sub(self, n)
+    }
+    fn saturating_add(self, n: u8) -> Self {
+        u8::saturating_add(self, n)
+    }
+    fn saturating_sub(self, n: u8) -> Self {
+        u8::saturating_sub(self, n)
+    }
+    fn is_zero(self) -> bool {
+        self == 0
+    }
+    fn to_byte(self) -> u8 {
+        self
+    }
+    fn from_byte(byte: u8) -> Self {
+        byte
+    }
+}
+
+impl Cell for u16 {
+    fn wrapping_add(self, n: u8) -> Self {
+        u16::wrapping_add(self, n as u16)
+    }
+    fn wrapping_sub(self, n: u8) -> Self {
+        u16::wrapping_sub(self, n as u16)
+    }
+    fn saturating_add(self, n: u8) -> Self {
+        u16::saturating_add(self, n as u16)
+    }
+    fn saturating_sub(self, n: u8) -> Self {
+        u16::saturating_sub(self, n as u16)
+    }
+    fn is_zero(self) -> bool {
+        self == 0
+    }
+    fn to_byte(self) -> u8 {
+        self as u8
+    }
+    fn from_byte(byte: u8) -> Self {
+        byte as u16
+    }
+}
+
+struct Tape<C> {
+    left: Vec<C>,
+    curr: C,
+    right: Vec<C>,
+}
+
+impl<C: Cell> Tape<C> {
+    fn new() -> Tape<C> {
+        Tape {
+            left: vec![C::default(); 8],
+            curr: C::default(),
+            right: vec![C::default(); 8],
+        }
+    }
+
+    fn inc(&mut self, n: u8, overflow: Overflow) {
+        self.curr = match overflow {
+            Overflow::Wrap => self.curr.wrapping_add(n),
+            Overflow::Saturate => self.curr.saturating_add(n),
+        };
+    }
+
+    fn dec(&mut self, n: u8, overflow: Overflow) {
+        self.curr = match overflow {
+            Overflow::Wrap => self.curr.wrapping_sub(n),
+            Overflow::Saturate => self.curr.saturating_sub(n),
+        };
+    }
+
+    fn move_left(&mut self, n: usize) {
+        for _ in 0..n {
+            self.right.push(self.curr);
+            match self.left.pop() {
+                Some(c) => self.curr = c,
+                None => self.curr = C::default(),
+            }
+        }
+    }
+
+    fn move_right(&mut self, n: usize) {
+        for _ in 0..n {
+            self.left.push(self.curr);
+            match self.right.pop() {
+                Some(c) => self.curr = c,
+                None => self.curr = C::default(),
+            }
+        }
+    }
+}
+
+/// An error from [`Interpreter::run`]: I/O failed while reading a `,` or
+/// writing a `.`.
+#[derive(Debug)]
+pub struct RunError(io::Error);
+
+impl fmt::Display for RunError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for RunError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.0)
+    }
+}
+
+impl From<io::Error> for RunError {
+    fn from(err: io::Error) -> RunError {
+        RunError(err)
+    }
+}
+
+/// Tree-walking interpreter that reads `,` input from `R` and writes `.`
+/// output to `W`, rather than hardcoding `stdin`/stdout. Generic over the
+/// cell type `C` (see [`Cell`]); defaults to the classic 8-bit cell.
+pub struct Interpreter<R, W, C = u8> {
+    input: R,
+    output: W,
+    overflow: Overflow,
+    _cell: PhantomData<C>,
+}
+
+impl<R: Read, W: Write, C: Cell> Interpreter<R, W, C> {
+    pub fn new(input: R, output: W) -> Interpreter<R, W, C> {
+        Interpreter {
+            input,
+            output,
+            overflow: Overflow::Wrap,
+            _cell: PhantomData,
+        }
+    }
+
+    /// Sets how `+`/`-` behave when a cell would run past its cell type's
+    /// range. Defaults to [`Overflow::Wrap`].
+    pub fn with_overflow(mut self, overflow: Overflow) -> Interpreter<R, W, C> {
+        self.overflow = overflow;
+        self
+    }
+
+    /// Runs `program` to completion, or returns the first I/O error.
+    pub fn run(&mut self, program: &Program) -> Result<(), RunError> {
+        let bytecode = &program.bytecode;
+        let mut tape = Tape::<C>::new();
+        let mut idx: usize = 0;
+
+        while idx < bytecode.len() {
+            match bytecode[idx] {
+                OpCode::Right(n) => tape.move_right(n),
+                OpCode::Left(n) => tape.move_left(n),
+                OpCode::Inc(n) => tape.inc(n, self.overflow),
+                OpCode::Dec(n) => tape.dec(n, self.overflow),
+                OpCode::Print => write!(self.output, "{}", tape.curr.to_byte() as char)?,
+                OpCode::Read => {
+                    let mut byte = [0u8; 1];
+                    tape.curr = match self.input.read(&mut byte)? {
+                        0 => C::default(),
+                        _ => C::from_byte(byte[0]),
+                    };
+                }
+                OpCode::JumpIfZero(j) => {
+                    if tape.curr.is_zero() {
+                        idx = j;
+                        continue;
+                    }
+                }
+                OpCode::Jump(j) => {
+                    idx = j;
+                    continue;
+                }
+                OpCode::SetZero => tape.curr = C::default(),
+                OpCode::SeekZero(step) => {
+                    while !tape.curr.is_zero() {
+                        if step >= 0 {
+                            tape.move_right(step as usize);
+                        } else {
+                            tape.move_left((-step) as usize);
+                        }
+                    }
+                }
+            }
+
+            idx += 1;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn runs_hello_world_and_captures_output() {
+        let source = "++++++++[>++++[>++>+++>+++>+<<<<-]>+>+>->>+[<]<-]>>.>---.+++++++..+++.>>.<-.<.+++.------.--------.>>+.>++.";
+        let program = Program::compile(source).unwrap();
+        let mut output = Vec::new();
+        let mut interpreter: Interpreter<_, _> =
+            Interpreter::new(Cursor::new(Vec::new()), &mut output);
+        interpreter.run(&program).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "Hello World!\n");
+    }
+
+    #[test]
+    fn echoes_fixed_input() {
+        let program = Program::compile(",.,.,.").unwrap();
+        let mut output = Vec::new();
+        let mut interpreter: Interpreter<_, _> =
+            Interpreter::new(Cursor::new(b"abc".to_vec()), &mut output);
+        interpreter.run(&program).unwrap();
+        assert_eq!(output, b"abc");
+    }
+
+    #[test]
+    fn wraps_by_default_but_saturates_when_configured() {
+        // `-` once on a fresh (zero) cell, then print it.
+        let program = Program::compile("-.").unwrap();
+
+        let mut wrapped = Vec::new();
+        let mut interpreter: Interpreter<_, _> =
+            Interpreter::new(Cursor::new(Vec::new()), &mut wrapped);
+        interpreter.run(&program).unwrap();
+        assert_eq!(
+            String::from_utf8(wrapped).unwrap(),
+            (255u8 as char).to_string()
+        );
+
+        let mut saturated = Vec::new();
+        let interpreter: Interpreter<_, _> =
+            Interpreter::new(Cursor::new(Vec::new()), &mut saturated);
+        let mut interpreter = interpreter.with_overflow(Overflow::Saturate);
+        interpreter.run(&program).unwrap();
+        assert_eq!(
+            String::from_utf8(saturated).unwrap(),
+            (0u8 as char).to_string()
+        );
+    }
+
+    #[test]
+    fn cell_type_is_configurable_via_generic_parameter() {
+        let mut cell_u8 = Tape::<u8>::new();
+        for _ in 0..256 {
+            cell_u8.inc(1, Overflow::Wrap);
+        }
+        assert!(cell_u8.curr.is_zero());
+
+        let mut cell_u16 = Tape::<u16>::new();
+        for _ in 0..256 {
+            cell_u16.inc(1, Overflow::Wrap);
+        }
+        assert_eq!(cell_u16.curr, 256);
+    }
+}
@@ -0,0 +1,222 @@
+//! Bytecode optimizer.
+//!
+//! Sits between `compile` and `Interpreter::run`/`jit::run`, rewriting the naive
+//! one-opcode-per-token stream into a denser form: runs of identical
+//! `Right`/`Left`/`Inc`/`Dec` collapse into a single counted opcode, and the
+//! idiomatic `[-]`/`[+]` clear loop and `[>]`/`[<]` scan loop collapse into
+//! dedicated `SetZero`/`SeekZero` opcodes. Both passes shrink the opcode
+//! stream, so jump targets are recomputed to match.
+
+use crate::OpCode;
+
+/// Runs every optimization pass over `bytecode`.
+pub fn optimize(bytecode: &[OpCode]) -> Vec<OpCode> {
+    let coalesced = coalesce_runs(bytecode);
+    coalesce_idioms(&coalesced)
+}
+
+/// Collapses consecutive identical `Right`/`Left`/`Inc`/`Dec` into a single
+/// counted opcode (a run of N `+` becomes `Inc(N)`).
+fn coalesce_runs(bytecode: &[OpCode]) -> Vec<OpCode> {
+    let mut index_map = vec![0usize; bytecode.len() + 1];
+    let mut out = Vec::with_capacity(bytecode.len());
+    let mut i = 0;
+
+    while i < bytecode.len() {
+        match bytecode[i] {
+            OpCode::Right(_) => {
+                let mut n = 0usize;
+                while i < bytecode.len() {
+                    match bytecode[i] {
+                        OpCode::Right(step) => n += step,
+                        _ => break,
+                    }
+                    index_map[i] = out.len();
+                    i += 1;
+                }
+                out.push(OpCode::Right(n));
+            }
+            OpCode::Left(_) => {
+                let mut n = 0usize;
+                while i < bytecode.len() {
+                    match bytecode[i] {
+                        OpCode::Left(step) => n += step,
+                        _ => break,
+                    }
+                    index_map[i] = out.len();
+                    i += 1;
+                }
+                out.push(OpCode::Left(n));
+            }
+            OpCode::Inc(_) => {
+                let mut n = 0u8;
+                while i < bytecode.len() {
+                    match bytecode[i] {
+                        OpCode::Inc(step) => n = n.wrapping_add(step),
+                        _ => break,
+                    }
+                    index_map[i] = out.len();
+                    i += 1;
+                }
+                out.push(OpCode::Inc(n));
+            }
+            OpCode::Dec(_) => {
+                let mut n = 0u8;
+                while i < bytecode.len() {
+                    match bytecode[i] {
+                        OpCode::Dec(step) => n = n.wrapping_add(step),
+                        _ => break,
+                    }
+                    index_map[i] = out.len();
+                    i += 1;
+                }
+                out.push(OpCode::Dec(n));
+            }
+            op => {
+                index_map[i] = out.len();
+                out.push(op);
+                i += 1;
+            }
+        }
+    }
+    index_map[bytecode.len()] = out.len();
+
+    remap_jumps(&mut out, &index_map);
+    out
+}
+
+/// Recognizes the three-opcode clear loop (`[-]`/`[+]`) and scan loop
+/// (`[>]`/`[<]`) idioms and replaces each with a single `SetZero`/`SeekZero`.
+fn coalesce_idioms(bytecode: &[OpCode]) -> Vec<OpCode> {
+    let mut index_map = vec![0usize; bytecode.len() + 1];
+    let mut out = Vec::with_capacity(bytecode.len());
+    let mut i = 0;
+
+    while i < bytecode.len() {
+        if let Some(op) = match_idiom(bytecode, i) {
+            index_map[i] = out.len();
+            index_map[i + 1] = out.len();
+            index_map[i + 2] = out.len();
+            out.push(op);
+            i += 3;
+        } else {
+            index_map[i] = out.len();
+            out.push(bytecode[i]);
+            i += 1;
+        }
+    }
+    index_map[bytecode.len()] = out.len();
+
+    remap_jumps(&mut out, &index_map);
+    out
+}
+
+/// Matches a `[ <op> ]` loop at `i` whose body is the single opcode `<op>`,
+/// and returns its replacement if `<op>` is one of the recognized idioms.
+fn match_idiom(bytecode: &[OpCode], i: usize) -> Option<OpCode> {
+    if i + 2 >= bytecode.len() {
+        return None;
+    }
+
+    let (after, back) = match (bytecode[i], bytecode[i + 2]) {
+        (OpCode::JumpIfZero(after), OpCode::Jump(back)) => (after, back),
+        _ => return None,
+    };
+    if after != i + 3 || back != i {
+        return None;
+    }
+
+    match bytecode[i + 1] {
+        OpCode::Inc(1) | OpCode::Dec(1) => Some(OpCode::SetZero),
+        OpCode::Right(n) => Some(OpCode::SeekZero(n as isize)),
+        OpCode::Left(n) => Some(OpCode::SeekZero(-(n as isize))),
+        _ => None,
+    }
+}
+
+/// Rewrites every `JumpIfZero`/`Jump` target in `ops` from an index into the
+/// pre-rewrite stream to the corresponding index into `ops` itself.
+fn remap_jumps(ops: &mut [OpCode], index_map: &[usize]) {
+    for op in ops.iter_mut() {
+        match op {
+            OpCode::JumpIfZero(target) => *target = index_map[*target],
+            OpCode::Jump(target) => *target = index_map[*target],
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{compile, parse, Tape};
+
+    /// A plain bytecode interpreter that collects `Print` output into a
+    /// buffer instead of writing to stdout, so tests can compare the naive
+    /// and optimized programs byte-for-byte.
+    fn run(bytecode: &[OpCode]) -> Vec<u8> {
+        let mut tape = Tape::<u8>::new();
+        let mut idx = 0;
+        let mut output = Vec::new();
+
+        while idx < bytecode.len() {
+            match bytecode[idx] {
+                OpCode::Right(n) => tape.move_right(n),
+                OpCode::Left(n) => tape.move_left(n),
+                OpCode::Inc(n) => tape.curr = tape.curr.wrapping_add(n),
+                OpCode::Dec(n) => tape.curr = tape.curr.wrapping_sub(n),
+                OpCode::Print => output.push(tape.curr),
+                OpCode::Read => tape.curr = 0,
+                OpCode::JumpIfZero(j) => {
+                    if tape.curr == 0 {
+                        idx = j;
+                        continue;
+                    }
+                }
+                OpCode::Jump(j) => {
+                    idx = j;
+                    continue;
+                }
+                OpCode::SetZero => tape.curr = 0,
+                OpCode::SeekZero(step) => {
+                    while tape.curr != 0 {
+                        if step >= 0 {
+                            tape.move_right(step as usize);
+                        } else {
+                            tape.move_left((-step) as usize);
+                        }
+                    }
+                }
+            }
+            idx += 1;
+        }
+
+        output
+    }
+
+    fn assert_same_output(source: &str) {
+        let naive = compile(&parse(source)).unwrap();
+        let optimized = optimize(&naive);
+        assert_eq!(run(&naive), run(&optimized), "program: {source}");
+    }
+
+    #[test]
+    fn coalesces_runs_of_identical_ops() {
+        assert_same_output("++++++++[>++++++++<-]>.");
+    }
+
+    #[test]
+    fn clear_loop_idiom() {
+        assert_same_output("+++++[-]+.");
+    }
+
+    #[test]
+    fn scan_loop_idiom() {
+        assert_same_output("+++>+++>[-]<<[>]+.");
+    }
+
+    #[test]
+    fn nested_loops() {
+        assert_same_output("++++[>+++[>++<-]<-]>>.");
+    }
+}